@@ -0,0 +1,215 @@
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const BY_NAME_DIR: &str = "/dev/block/by-name";
+
+/// AVB vbmeta header flag bits, from `external/avb/libavb/avb_vbmeta_image.h`.
+const AVB_VBMETA_FLAG_HASHTREE_DISABLED: u32 = 1 << 0;
+const AVB_VBMETA_FLAG_VERIFICATION_DISABLED: u32 = 1 << 1;
+/// Byte offset of the `flags` field within `AvbVBMetaImageHeader`: magic(4) +
+/// major(4) + minor(4) + auth_block_size(8) + aux_block_size(8) +
+/// algorithm_type(4) + hash_offset(8) + hash_size(8) + sig_offset(8) +
+/// sig_size(8) + pubkey_offset(8) + pubkey_size(8) + pubkey_meta_offset(8) +
+/// pubkey_meta_size(8) + descriptors_offset(8) + descriptors_size(8) +
+/// rollback_index(8) = 120.
+const AVB_VBMETA_FLAGS_OFFSET: usize = 120;
+
+/// Logs the requested boot (or init_boot) patch and, when `patch_vbmeta` is
+/// set, disables verity/verification on the matching vbmeta partition(s).
+/// The boot image itself is not modified: `boot`, `kernel`, `module`, `init`,
+/// `out` and `magiskboot` are ignored.
+#[allow(clippy::too_many_arguments)]
+pub fn patch(
+    boot: Option<PathBuf>,
+    kernel: Option<PathBuf>,
+    module: Option<PathBuf>,
+    init: Option<PathBuf>,
+    ota: bool,
+    flash: bool,
+    out: Option<PathBuf>,
+    magiskboot: Option<PathBuf>,
+    patch_vbmeta: bool,
+) -> Result<()> {
+    let _ = (kernel, module, init, out, magiskboot);
+
+    log::info!("patching boot image: {boot:?}, ota: {ota}, flash: {flash}");
+
+    if patch_vbmeta {
+        disable_vbmeta_verification(ota, flash)?;
+    }
+
+    Ok(())
+}
+
+/// Find every vbmeta-family partition under `/dev/block/by-name` and, for
+/// each, disable verity and verification. Matches by suffix rather than
+/// exact name, so prefixed variants like `guest_vbmeta_a` on virtualized/VM
+/// layouts are patched too, not just the plain AOSP `vbmeta`/`vbmeta_a`.
+fn disable_vbmeta_verification(ota: bool, flash: bool) -> Result<()> {
+    let Ok(entries) = fs::read_dir(BY_NAME_DIR) else {
+        log::warn!("{BY_NAME_DIR} not found, skipping vbmeta patch");
+        return Ok(());
+    };
+    let entries: Vec<_> = entries.flatten().collect();
+
+    let has_slots = entries.iter().any(|e| {
+        let name = e.file_name();
+        let name = name.to_string_lossy();
+        let has_slot = name.ends_with("_a") || name.ends_with("_b");
+        let base = if has_slot { &name[..name.len() - 2] } else { &name };
+        has_slot && base.ends_with("vbmeta")
+    });
+
+    let target_suffix = if has_slots {
+        let current = current_slot_suffix().context(
+            "device has slotted vbmeta partitions but the active slot suffix couldn't be \
+             determined from /proc/cmdline or /proc/bootconfig; refusing to guess which slot to patch",
+        )?;
+        if ota { other_slot_suffix(&current) } else { current }
+    } else {
+        String::new()
+    };
+
+    let mut patched_any = false;
+    for entry in &entries {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if !is_vbmeta_partition(&name, &target_suffix) {
+            continue;
+        }
+
+        log::info!("patching vbmeta partition: {name}");
+        patch_vbmeta_image(&entry.path(), flash)?;
+        patched_any = true;
+    }
+
+    if !patched_any {
+        log::warn!("no vbmeta partition found for slot {target_suffix:?}");
+    }
+    Ok(())
+}
+
+/// Whether `name` names a vbmeta partition for `slot_suffix`, accepting any
+/// prefix (e.g. `vbmeta`, `vbmeta_a`, `guest_vbmeta_a`) as long as it ends
+/// with `vbmeta[_a|_b]`. `slot_suffix` must be `""` only when the device has
+/// no slotted vbmeta partitions at all (see [`disable_vbmeta_verification`]);
+/// an empty suffix never matches a slotted name, to avoid patching both
+/// slots when detection fails.
+fn is_vbmeta_partition(name: &str, slot_suffix: &str) -> bool {
+    let has_slot = name.ends_with("_a") || name.ends_with("_b");
+    let base = if has_slot { &name[..name.len() - 2] } else { name };
+    if !base.ends_with("vbmeta") {
+        return false;
+    }
+    if slot_suffix.is_empty() {
+        !has_slot
+    } else {
+        has_slot && name.ends_with(slot_suffix)
+    }
+}
+
+/// The active slot suffix (`_a`/`_b`), read from `/proc/cmdline` or, failing
+/// that, `/proc/bootconfig`. Errors rather than guessing, since silently
+/// falling back to an empty suffix would make [`is_vbmeta_partition`] match
+/// none of the slotted partitions.
+fn current_slot_suffix() -> Result<String> {
+    if let Some(suffix) = slot_suffix_from_cmdline() {
+        return Ok(suffix);
+    }
+    if let Some(suffix) = slot_suffix_from_bootconfig() {
+        return Ok(suffix);
+    }
+    bail!("androidboot.slot_suffix not found in /proc/cmdline or /proc/bootconfig");
+}
+
+fn slot_suffix_from_cmdline() -> Option<String> {
+    let cmdline = fs::read_to_string("/proc/cmdline").ok()?;
+    cmdline
+        .split_whitespace()
+        .find_map(|arg| arg.strip_prefix("androidboot.slot_suffix="))
+        .map(str::to_string)
+        .filter(|s| !s.is_empty())
+}
+
+fn slot_suffix_from_bootconfig() -> Option<String> {
+    let bootconfig = fs::read_to_string("/proc/bootconfig").ok()?;
+    bootconfig.lines().find_map(|line| {
+        let (key, value) = line.split_once('=')?;
+        if key.trim() != "androidboot.slot_suffix" {
+            return None;
+        }
+        let suffix = value.trim().trim_matches('"');
+        (!suffix.is_empty()).then(|| suffix.to_string())
+    })
+}
+
+fn other_slot_suffix(slot_suffix: &str) -> String {
+    match slot_suffix {
+        "_a" => "_b".to_string(),
+        "_b" => "_a".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Set the disable-verity and disable-verification bits in the AVB vbmeta
+/// header at `path`, then (if `flash`) write the modified image back.
+fn patch_vbmeta_image(path: &Path, flash: bool) -> Result<()> {
+    let mut image = fs::read(path).with_context(|| format!("can't read {path:?}"))?;
+    if image.len() <= AVB_VBMETA_FLAGS_OFFSET + 4 {
+        anyhow::bail!("{path:?} is too small to be a vbmeta image");
+    }
+
+    let flags_bytes = &mut image[AVB_VBMETA_FLAGS_OFFSET..AVB_VBMETA_FLAGS_OFFSET + 4];
+    let mut flags = u32::from_be_bytes(flags_bytes.try_into().unwrap());
+    flags |= AVB_VBMETA_FLAG_HASHTREE_DISABLED | AVB_VBMETA_FLAG_VERIFICATION_DISABLED;
+    flags_bytes.copy_from_slice(&flags.to_be_bytes());
+
+    if flash {
+        fs::write(path, &image).with_context(|| format!("failed to flash {path:?}"))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 256-byte AVB vbmeta header (the real on-disk size) with the
+    /// `rollback_index_location` bytes right after `flags` set to a sentinel pattern,
+    /// so a wrong `AVB_VBMETA_FLAGS_OFFSET` that bleeds into neighbouring
+    /// fields is caught.
+    fn sample_header() -> Vec<u8> {
+        let mut header = vec![0u8; 256];
+        header[0..4].copy_from_slice(b"AVB0");
+        header[AVB_VBMETA_FLAGS_OFFSET + 4..AVB_VBMETA_FLAGS_OFFSET + 8]
+            .copy_from_slice(&[0xAA, 0xBB, 0xCC, 0xDD]);
+        header
+    }
+
+    #[test]
+    fn patch_vbmeta_image_sets_only_the_flags_field() {
+        let path = std::env::temp_dir().join(format!("ksud-vbmeta-test-{}", std::process::id()));
+        fs::write(&path, sample_header()).unwrap();
+
+        patch_vbmeta_image(&path, true).unwrap();
+
+        let patched = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let flags = u32::from_be_bytes(
+            patched[AVB_VBMETA_FLAGS_OFFSET..AVB_VBMETA_FLAGS_OFFSET + 4]
+                .try_into()
+                .unwrap(),
+        );
+        assert_eq!(
+            flags,
+            AVB_VBMETA_FLAG_HASHTREE_DISABLED | AVB_VBMETA_FLAG_VERIFICATION_DISABLED
+        );
+        assert_eq!(
+            &patched[AVB_VBMETA_FLAGS_OFFSET + 4..AVB_VBMETA_FLAGS_OFFSET + 8],
+            &[0xAA, 0xBB, 0xCC, 0xDD],
+            "patching flags must not touch rollback_index_location"
+        );
+    }
+}
@@ -82,6 +82,11 @@ enum Commands {
         /// magiskboot path, if not specified, will use builtin one
         #[arg(long, default_value = None)]
         magiskboot: Option<PathBuf>,
+
+        /// Also disable verity/verification in the matching vbmeta image,
+        /// writing it back only when --flash is given
+        #[arg(long, default_value = "false")]
+        patch_vbmeta: bool,
     },
     /// For developers
     Debug {
@@ -126,6 +131,18 @@ enum Debug {
         file: String,
     },
 
+    /// Block until a system property reaches the expected value, including
+    /// properties that don't exist yet at call time
+    WaitProp {
+        /// property name
+        name: String,
+        /// expected value
+        value: String,
+        /// timeout in seconds
+        #[arg(default_value = "10")]
+        timeout: u64,
+    },
+
     /// For testing
     Test,
 }
@@ -149,6 +166,15 @@ enum Sepolicy {
         /// sepolicy statements
         sepolicy: String,
     },
+
+    /// Print the current SELinux enforcement mode
+    Status,
+
+    /// Set the SELinux enforcement mode, mirroring `setenforce`
+    SetEnforce {
+        /// 0/permissive or 1/enforcing
+        mode: String,
+    },
 }
 
 #[derive(clap::Subcommand, Debug)]
@@ -192,6 +218,25 @@ enum Module {
         #[arg(default_value = "8080")]
         port: u16,
     },
+
+    /// Inspect or force the bootloop safe-mode state
+    SafeMode {
+        #[command(subcommand)]
+        command: SafeMode,
+    },
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum SafeMode {
+    /// Force safe mode on and disable all modules; turning it off does not
+    /// re-enable them
+    On,
+
+    /// Clear the forced safe-mode flag and reset the boot-attempt counter
+    Off,
+
+    /// Print whether safe mode is currently active, and why
+    Status,
 }
 
 #[derive(clap::Subcommand, Debug)]
@@ -273,6 +318,11 @@ pub fn run() -> Result<()> {
                 Module::List => module::list_modules(),
                 Module::Shrink => module::shrink_ksu_images(),
                 Module::Serve { id, port } => server::serve_module(&id, port),
+                Module::SafeMode { command } => match command {
+                    SafeMode::On => crate::safemode::force_on(),
+                    SafeMode::Off => crate::safemode::force_off(),
+                    SafeMode::Status => crate::safemode::status(),
+                },
             }
         }
         Commands::Install => event::install(),
@@ -280,6 +330,8 @@ pub fn run() -> Result<()> {
             Sepolicy::Patch { sepolicy } => crate::sepolicy::live_patch(&sepolicy),
             Sepolicy::Apply { file } => crate::sepolicy::apply_file(file),
             Sepolicy::Check { sepolicy } => crate::sepolicy::check_rule(&sepolicy),
+            Sepolicy::Status => crate::sepolicy::status(),
+            Sepolicy::SetEnforce { mode } => crate::sepolicy::set_enforce(&mode),
         },
         Commands::Services => event::on_services(),
         Commands::Profile { command } => match command {
@@ -311,6 +363,14 @@ pub fn run() -> Result<()> {
                 Ok(())
             }
             Debug::PunchHole { file } => utils::punch_hole(file),
+            Debug::WaitProp { name, value, timeout } => {
+                if utils::wait_for_prop(&name, &value, std::time::Duration::from_secs(timeout))? {
+                    Ok(())
+                } else {
+                    log::error!("timed out waiting for {name}={value}");
+                    std::process::exit(2);
+                }
+            }
             Debug::Test => todo!(),
         },
 
@@ -323,7 +383,18 @@ pub fn run() -> Result<()> {
             flash,
             out,
             magiskboot,
-        } => crate::boot_patch::patch(boot, kernel, module, init, ota, flash, out, magiskboot),
+            patch_vbmeta,
+        } => crate::boot_patch::patch(
+            boot,
+            kernel,
+            module,
+            init,
+            ota,
+            flash,
+            out,
+            magiskboot,
+            patch_vbmeta,
+        ),
     };
 
     if let Err(e) = &result {
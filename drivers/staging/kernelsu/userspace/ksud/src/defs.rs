@@ -0,0 +1,35 @@
+pub const WORKING_DIR: &str = "/data/adb/ksu/";
+pub const BINARY_DIR: &str = concat!("/data/adb/ksu/", "bin/");
+pub const DAEMON_PATH: &str = "/data/adb/ksud";
+
+pub const MODULE_DIR: &str = "/data/adb/modules/";
+pub const MODULE_UPDATE_TMP_DIR: &str = "/data/adb/modules_update/";
+pub const DISABLE_FILE_NAME: &str = "disable";
+pub const UPDATE_FILE_NAME: &str = "update";
+pub const REMOVE_FILE_NAME: &str = "remove";
+pub const SKIP_MOUNT_FILE_NAME: &str = "skip_mount";
+pub const MODULE_WEB_DIR: &str = "webroot";
+pub const PROP_FILE_NAME: &str = "module.prop";
+
+/// Bundled `secilc`, used to compile CIL policy fragments to binary before
+/// merging them into the loaded policy.
+pub const SECILC_PATH: &str = concat!("/data/adb/ksu/", "bin/secilc");
+
+/// Persisted SELinux mode that should be re-applied on every boot, once the
+/// kernel has finished loading policy. Absence of the file means "leave the
+/// boot-time mode alone".
+pub const SELINUX_BOOT_MODE_FILE: &str = concat!("/data/adb/ksu/", "selinux_mode");
+
+/// Count of consecutive boots that started `post-fs-data` without ever
+/// reaching `boot-complete`. Reset to zero on a successful boot.
+pub const BOOT_COUNT_FILE: &str = concat!("/data/adb/ksu/", "boot_count");
+/// Presence means the user forced safe mode on via `module safe-mode on`,
+/// independent of the boot-attempt counter.
+pub const SAFE_MODE_FORCE_FILE: &str = concat!("/data/adb/ksu/", "safe_mode_forced");
+/// Value of the boot-attempt counter, which includes the boot in progress,
+/// at which modules are auto-disabled: with 3, the boot that follows two
+/// failed boots runs in safe mode.
+pub const SAFE_MODE_BOOT_THRESHOLD: u32 = 3;
+
+pub const VERSION_CODE: i32 = 11994;
+pub const VERSION_NAME: &str = env!("CARGO_PKG_VERSION");
@@ -0,0 +1,47 @@
+use anyhow::Result;
+
+/// Runs on the `post-fs-data` trigger, before any module overlay is mounted.
+pub fn on_post_data_fs() -> Result<()> {
+    log::info!("on_post_data_fs triggered");
+
+    // The kernel has just finished loading policy; re-apply whatever
+    // enforcing mode was persisted via `sepolicy set-enforce`, since setting
+    // it any earlier than this is lost once policy load completes.
+    if let Err(e) = crate::sepolicy::restore_boot_mode() {
+        log::error!("failed to restore selinux mode: {:?}", e);
+    }
+
+    if crate::safemode::enter_boot()? {
+        log::warn!("safe mode active, skipping module mounts");
+        return Ok(());
+    }
+
+    crate::module::mount_modules()?;
+
+    Ok(())
+}
+
+/// Runs on the `service` trigger.
+pub fn on_services() -> Result<()> {
+    log::info!("on_services triggered");
+    Ok(())
+}
+
+/// Runs on the `boot-complete` trigger.
+pub fn on_boot_completed() -> Result<()> {
+    log::info!("on_boot_completed triggered");
+    crate::safemode::on_boot_completed()?;
+    Ok(())
+}
+
+/// Logs the install request; nothing is installed.
+pub fn install() -> Result<()> {
+    log::info!("install triggered");
+    Ok(())
+}
+
+/// Logs `module_dir`; nothing is mounted.
+pub fn mount_systemlessly(module_dir: &str) -> Result<()> {
+    log::info!("mount_systemlessly: {}", module_dir);
+    Ok(())
+}
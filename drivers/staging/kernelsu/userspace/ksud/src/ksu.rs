@@ -0,0 +1,98 @@
+use anyhow::{bail, Result};
+use std::path::Path;
+
+/// Magic value KernelSU's `prctl` hook checks for before dispatching any of
+/// the `CMD_*` sub-commands below.
+const KERNEL_SU_OPTION: i32 = 0xDEAD_BEEF_u32 as i32;
+
+const CMD_GRANT_ROOT: i32 = 0;
+const CMD_GET_VERSION: i32 = 2;
+const CMD_REPORT_EVENT: i32 = 3;
+const CMD_SET_SEPOLICY: i32 = 4;
+const CMD_CHECK_SEPOLICY: i32 = 5;
+const CMD_MERGE_BINARY_POLICY: i32 = 7;
+const CMD_GET_SAFE_MODE: i32 = 8;
+const CMD_SET_ENFORCE: i32 = 9;
+
+fn prctl(cmd: i32, arg2: usize, arg3: usize) -> i32 {
+    unsafe { libc::prctl(KERNEL_SU_OPTION, cmd, arg2, arg3, 0) }
+}
+
+/// Ask the kernel to grant root to the calling process.
+pub fn grant_root() -> Result<()> {
+    if prctl(CMD_GRANT_ROOT, 0, 0) != 0 {
+        bail!("grant root failed");
+    }
+    Ok(())
+}
+
+/// Get the KernelSU kernel module version.
+pub fn get_version() -> i32 {
+    prctl(CMD_GET_VERSION, 0, 0)
+}
+
+/// Replace the current process with a root shell, as done when invoked as
+/// `su`.
+pub fn root_shell() -> Result<()> {
+    grant_root()?;
+    let shell = std::ffi::CString::new("/system/bin/sh")?;
+    let argv = [shell.as_ptr(), std::ptr::null()];
+    unsafe {
+        libc::execv(shell.as_ptr(), argv.as_ptr());
+    }
+    bail!("failed to exec root shell: {}", std::io::Error::last_os_error())
+}
+
+/// Report a lifecycle event (e.g. post-fs-data, boot-completed) to the
+/// kernel module.
+pub fn report_event(event: i32) -> Result<()> {
+    if prctl(CMD_REPORT_EVENT, event as usize, 0) != 0 {
+        bail!("failed to report event {event}");
+    }
+    Ok(())
+}
+
+/// Apply a single sepolicy statement to the currently loaded, live policy.
+pub fn apply_sepolicy_rule(rule: &str) -> Result<()> {
+    let rule = std::ffi::CString::new(rule)?;
+    if prctl(CMD_SET_SEPOLICY, rule.as_ptr() as usize, 0) != 0 {
+        bail!("kernel rejected sepolicy rule");
+    }
+    Ok(())
+}
+
+/// Ask the kernel whether a sepolicy statement is syntactically valid,
+/// without applying it.
+pub fn check_sepolicy_rule(rule: &str) -> Result<()> {
+    let rule = std::ffi::CString::new(rule)?;
+    if prctl(CMD_CHECK_SEPOLICY, rule.as_ptr() as usize, 0) != 0 {
+        bail!("invalid sepolicy rule");
+    }
+    Ok(())
+}
+
+/// Set the SELinux enforcing mode directly through KernelSU's kernel hook,
+/// bypassing `/sys/fs/selinux/enforce` entirely. Used as a fallback when the
+/// sysfs write is rejected.
+pub fn set_enforce_via_kernel(enforcing: bool) -> Result<()> {
+    if prctl(CMD_SET_ENFORCE, enforcing as usize, 0) != 0 {
+        bail!("kernel rejected security_setenforce");
+    }
+    Ok(())
+}
+
+/// Whether the bootloader reported that the hardware safe-mode key combo
+/// (e.g. volume-down held during boot) was pressed this boot.
+pub fn hardware_safe_mode_signal() -> bool {
+    prctl(CMD_GET_SAFE_MODE, 0, 0) == 1
+}
+
+/// Merge a precompiled monolithic binary policy at `path` into the currently
+/// loaded policy.
+pub fn merge_binary_policy(path: &Path) -> Result<()> {
+    let path = std::ffi::CString::new(path.as_os_str().as_encoded_bytes())?;
+    if prctl(CMD_MERGE_BINARY_POLICY, path.as_ptr() as usize, 0) != 0 {
+        bail!("kernel rejected binary policy");
+    }
+    Ok(())
+}
@@ -0,0 +1,78 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+
+use crate::defs;
+
+fn module_dir(id: &str) -> std::path::PathBuf {
+    Path::new(defs::MODULE_DIR).join(id)
+}
+
+/// Logs the zip path; the module is not installed.
+pub fn install_module(zip: &str) -> Result<()> {
+    log::info!("install_module: {}", zip);
+    Ok(())
+}
+
+/// Mark a module for removal on next boot.
+pub fn uninstall_module(id: &str) -> Result<()> {
+    log::info!("uninstall_module: {}", id);
+    fs::write(module_dir(id).join(defs::REMOVE_FILE_NAME), "")
+        .with_context(|| format!("failed to mark module {id} for removal"))
+}
+
+/// Enable a previously disabled module.
+pub fn enable_module(id: &str) -> Result<()> {
+    log::info!("enable_module: {}", id);
+    let disable_file = module_dir(id).join(defs::DISABLE_FILE_NAME);
+    if disable_file.exists() {
+        fs::remove_file(disable_file).with_context(|| format!("failed to enable module {id}"))?;
+    }
+    Ok(())
+}
+
+/// Disable a module without uninstalling it.
+pub fn disable_module(id: &str) -> Result<()> {
+    log::info!("disable_module: {}", id);
+    fs::write(module_dir(id).join(defs::DISABLE_FILE_NAME), "")
+        .with_context(|| format!("failed to disable module {id}"))
+}
+
+/// Disable every installed module, the same way [`disable_module`] disables
+/// one. Used by safe mode to recover an unbootable device.
+pub fn disable_all_modules() -> Result<()> {
+    let Ok(entries) = fs::read_dir(defs::MODULE_DIR) else {
+        return Ok(());
+    };
+    for entry in entries.flatten() {
+        let id = entry.file_name();
+        let id = id.to_string_lossy();
+        if let Err(e) = disable_module(&id) {
+            log::error!("failed to disable module {id} for safe mode: {:?}", e);
+        }
+    }
+    Ok(())
+}
+
+/// List all installed modules.
+pub fn list_modules() -> Result<()> {
+    let Ok(entries) = fs::read_dir(defs::MODULE_DIR) else {
+        return Ok(());
+    };
+    for entry in entries.flatten() {
+        println!("{}", entry.file_name().to_string_lossy());
+    }
+    Ok(())
+}
+
+/// Logs the call; the KernelSU images are not resized.
+pub fn shrink_ksu_images() -> Result<()> {
+    log::info!("shrink_ksu_images");
+    Ok(())
+}
+
+/// Called from `post-fs-data`. Logs the call; no module overlay is mounted.
+pub fn mount_modules() -> Result<()> {
+    log::info!("mount_modules");
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use anyhow::{Context, Result};
+use std::fs;
+
+use crate::defs;
+
+fn read_boot_count() -> u32 {
+    fs::read_to_string(defs::BOOT_COUNT_FILE)
+        .ok()
+        .and_then(|s| s.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+fn write_boot_count(count: u32) -> Result<()> {
+    fs::write(defs::BOOT_COUNT_FILE, count.to_string())
+        .context("failed to persist boot attempt counter")
+}
+
+fn is_forced() -> bool {
+    std::path::Path::new(defs::SAFE_MODE_FORCE_FILE).exists()
+}
+
+/// Whether safe mode should be in effect right now: forced by the user,
+/// signalled by the bootloader's hardware key-combo, or the boot-attempt
+/// counter has crossed [`defs::SAFE_MODE_BOOT_THRESHOLD`].
+pub fn is_active() -> bool {
+    is_forced() || crate::ksu::hardware_safe_mode_signal() || read_boot_count() >= defs::SAFE_MODE_BOOT_THRESHOLD
+}
+
+/// Called from `post-fs-data`, before any module overlay is mounted.
+/// Increments the boot-attempt counter and, if safe mode is now active,
+/// disables every module so the device can recover from a module that
+/// bricks boot. Returns whether safe mode is active.
+pub fn enter_boot() -> Result<bool> {
+    write_boot_count(read_boot_count() + 1)?;
+
+    let active = is_active();
+    if active {
+        log::warn!("safe mode active, disabling all modules");
+        crate::module::disable_all_modules()?;
+    }
+    Ok(active)
+}
+
+/// Called from `boot-complete`: the boot succeeded, so reset the
+/// consecutive-failure counter.
+pub fn on_boot_completed() -> Result<()> {
+    write_boot_count(0)
+}
+
+/// `module safe-mode on`: force safe mode regardless of the boot counter, and
+/// disable every module immediately rather than waiting for the next boot's
+/// `enter_boot()` to do it.
+pub fn force_on() -> Result<()> {
+    fs::write(defs::SAFE_MODE_FORCE_FILE, "").context("failed to force safe mode on")?;
+    crate::module::disable_all_modules()
+}
+
+/// `module safe-mode off`: clear the forced flag and reset the boot counter.
+pub fn force_off() -> Result<()> {
+    let _ = fs::remove_file(defs::SAFE_MODE_FORCE_FILE);
+    write_boot_count(0)
+}
+
+/// `module safe-mode status`: print whether safe mode is currently active,
+/// and why.
+pub fn status() -> Result<()> {
+    if is_forced() {
+        println!("active (forced)");
+    } else if crate::ksu::hardware_safe_mode_signal() {
+        println!("active (hardware key-combo)");
+    } else {
+        let count = read_boot_count();
+        if count >= defs::SAFE_MODE_BOOT_THRESHOLD {
+            println!("active ({count} consecutive boot failures)");
+        } else {
+            println!("inactive ({count} consecutive boot failures)");
+        }
+    }
+    Ok(())
+}
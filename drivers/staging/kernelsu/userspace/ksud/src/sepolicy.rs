@@ -0,0 +1,197 @@
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+const SELINUX_ENFORCE_FILE: &str = "/sys/fs/selinux/enforce";
+const SELINUX_POLICYVERS_FILE: &str = "/sys/fs/selinux/policyvers";
+
+/// `POLICYDB_MAGIC`: the leading 4 bytes of a precompiled monolithic binary
+/// policy, as shipped at `/sepolicy` on non-split devices.
+const POLICYDB_MAGIC: [u8; 4] = [0x8c, 0xff, 0x7c, 0xf9];
+
+/// Highest policy database version this build knows how to target when
+/// compiling CIL. Clamped against the running kernel's `policyvers`, since a
+/// module might ship CIL newer than what an older kernel understands.
+const MAX_POLICY_VERSION: u32 = 33;
+
+enum PolicyFormat {
+    /// One `;`-separated live-patch statement per (non-comment) line.
+    Statements,
+    /// Android's CIL policy language, as used for split plat/vendor policy.
+    Cil,
+    /// A precompiled monolithic binary policy, as shipped at `/sepolicy`.
+    Binary,
+}
+
+fn detect_format(path: &Path) -> Result<PolicyFormat> {
+    if path.extension().is_some_and(|ext| ext == "cil") {
+        return Ok(PolicyFormat::Cil);
+    }
+
+    let mut magic = [0u8; 4];
+    let mut file = fs::File::open(path).with_context(|| format!("can't open {path:?}"))?;
+    if file.read_exact(&mut magic).is_ok() && magic == POLICYDB_MAGIC {
+        return Ok(PolicyFormat::Binary);
+    }
+
+    Ok(PolicyFormat::Statements)
+}
+
+/// Read the running kernel's maximum supported policy database version,
+/// clamped to [`MAX_POLICY_VERSION`].
+fn running_policy_version() -> Result<u32> {
+    let content = fs::read_to_string(SELINUX_POLICYVERS_FILE)
+        .with_context(|| format!("can't read {SELINUX_POLICYVERS_FILE}"))?;
+    let version: u32 = content.trim().parse().context("invalid policyvers")?;
+    Ok(version.min(MAX_POLICY_VERSION))
+}
+
+/// Apply a live sepolicy patch, expressed as `;`-separated statements, to the
+/// currently loaded policy via KernelSU's `prctl` hook.
+pub fn live_patch(sepolicy: &str) -> Result<()> {
+    for rule in sepolicy.split(';').map(str::trim).filter(|s| !s.is_empty()) {
+        log::info!("patch sepolicy: {}", rule);
+        crate::ksu::apply_sepolicy_rule(rule)
+            .with_context(|| format!("failed to apply rule: {rule}"))?;
+    }
+    Ok(())
+}
+
+/// Apply a sepolicy `file`, auto-detecting whether it holds textual
+/// live-patch statements, CIL, or a precompiled binary policy.
+pub fn apply_file(file: String) -> Result<()> {
+    let path = Path::new(&file);
+    match detect_format(path)? {
+        PolicyFormat::Statements => apply_statements_file(&file),
+        PolicyFormat::Cil => apply_cil_file(path),
+        PolicyFormat::Binary => apply_binary_file(path),
+    }
+}
+
+/// Apply sepolicy statements read from `file`, one statement per line.
+fn apply_statements_file(file: &str) -> Result<()> {
+    let statements = fs::read_to_string(file).with_context(|| format!("can't read {file}"))?;
+    for line in statements.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        live_patch(line)?;
+    }
+    Ok(())
+}
+
+/// Compile a CIL fragment with the bundled `secilc`, targeting the running
+/// kernel's supported policy version, then merge the resulting binary policy
+/// into the currently loaded policy rather than replacing it, so a module
+/// can ship a versioned fragment instead of hand-written live-patch
+/// statements.
+fn apply_cil_file(path: &Path) -> Result<()> {
+    let version = running_policy_version()?;
+    log::info!("compiling {path:?} as CIL, targeting policy version {version}");
+
+    let compiled = compile_cil(path, version)
+        .with_context(|| format!("failed to compile CIL policy {path:?}"))?;
+    let result = crate::ksu::merge_binary_policy(&compiled)
+        .with_context(|| format!("failed to merge compiled CIL policy {path:?}"));
+    let _ = fs::remove_file(&compiled);
+    result
+}
+
+/// Invoke `secilc` to compile `path` into a binary policy targeting
+/// `policy_version`, returning the path of the compiled output.
+fn compile_cil(path: &Path, policy_version: u32) -> Result<std::path::PathBuf> {
+    let out = std::env::temp_dir().join(format!("ksud-cil-{}.bin", std::process::id()));
+    let status = std::process::Command::new(crate::defs::SECILC_PATH)
+        .args(["-m", "-M", "true", "-G", "-N"])
+        .arg("-c")
+        .arg(policy_version.to_string())
+        .arg("-o")
+        .arg(&out)
+        .arg("-f")
+        .arg("/dev/null")
+        .arg(path)
+        .status()
+        .with_context(|| format!("failed to launch {}", crate::defs::SECILC_PATH))?;
+
+    if !status.success() {
+        bail!("secilc failed to compile {path:?}: {status}");
+    }
+    Ok(out)
+}
+
+/// Merge a precompiled monolithic binary policy into the currently loaded
+/// policy.
+fn apply_binary_file(path: &Path) -> Result<()> {
+    log::info!("merging precompiled binary policy {path:?}");
+    crate::ksu::merge_binary_policy(path)
+        .with_context(|| format!("failed to merge binary policy {path:?}"))
+}
+
+/// Check whether `sepolicy` is a syntactically valid, supported statement,
+/// without applying it.
+pub fn check_rule(sepolicy: &str) -> Result<()> {
+    for rule in sepolicy.split(';').map(str::trim).filter(|s| !s.is_empty()) {
+        crate::ksu::check_sepolicy_rule(rule).with_context(|| format!("invalid rule: {rule}"))?;
+    }
+    Ok(())
+}
+
+/// Print the current SELinux enforcement mode, mirroring `getenforce`.
+pub fn status() -> Result<()> {
+    println!("{}", if is_enforcing()? { "Enforcing" } else { "Permissive" });
+    Ok(())
+}
+
+fn is_enforcing() -> Result<bool> {
+    let content = fs::read_to_string(SELINUX_ENFORCE_FILE)
+        .with_context(|| format!("can't read {SELINUX_ENFORCE_FILE}"))?;
+    Ok(content.trim() == "1")
+}
+
+/// Parse a `setenforce`-style mode argument (`0`/`permissive`/`1`/`enforcing`).
+fn parse_mode(mode: &str) -> Result<bool> {
+    match mode.to_ascii_lowercase().as_str() {
+        "0" | "permissive" => Ok(false),
+        "1" | "enforcing" => Ok(true),
+        _ => bail!("invalid mode: {mode}, expected 0/permissive or 1/enforcing"),
+    }
+}
+
+/// Equivalent of `setenforce`, and persist the chosen mode so it can be
+/// re-applied by [`crate::event::on_post_data_fs`] after every boot, since a
+/// mode set this way is otherwise lost once the kernel reloads policy.
+pub fn set_enforce(mode: &str) -> Result<()> {
+    let enforcing = parse_mode(mode)?;
+    write_enforce(enforcing)?;
+    fs::write(crate::defs::SELINUX_BOOT_MODE_FILE, if enforcing { "1" } else { "0" })
+        .context("failed to persist selinux mode")?;
+    Ok(())
+}
+
+/// Re-apply the persisted SELinux mode, if any was recorded by
+/// [`set_enforce`]. Called early in `post-fs-data`, right after the kernel
+/// has finished loading policy, since setting the mode before that point is
+/// lost.
+pub fn restore_boot_mode() -> Result<()> {
+    let Ok(content) = fs::read_to_string(crate::defs::SELINUX_BOOT_MODE_FILE) else {
+        return Ok(());
+    };
+    let enforcing = content.trim() == "1";
+    write_enforce(enforcing)
+}
+
+/// Write the enforcing flag to `/sys/fs/selinux/enforce`, falling back to
+/// KernelSU's own `security_setenforce` kernel hook when the sysfs write is
+/// rejected (e.g. selinuxfs mounted read-only for the caller's domain). The
+/// fallback goes through the kernel module directly rather than sysfs, so it
+/// succeeds in cases the primary path can't.
+fn write_enforce(enforcing: bool) -> Result<()> {
+    let value = if enforcing { b"1" as &[u8] } else { b"0" };
+    if fs::write(SELINUX_ENFORCE_FILE, value).is_ok() {
+        return Ok(());
+    }
+
+    crate::ksu::set_enforce_via_kernel(enforcing).context("security_setenforce fallback failed")
+}
@@ -0,0 +1,185 @@
+use anyhow::{Context, Result};
+use std::os::unix::io::RawFd;
+
+/// Switch into the mount namespace of `pid`.
+///
+/// Prefers `pidfd_open(2)` + `setns(pidfd, CLONE_NEWNS)` (Linux 5.8+), which
+/// skips the `/proc/<pid>/ns/mnt` path lookup and closes the pid-reuse TOCTOU
+/// window between resolving `pid` and entering its namespace. Falls back to
+/// the `/proc` path when the pidfd path reports `EINVAL`/`ENOSYS`, i.e. the
+/// running kernel is too old to support it.
+pub fn switch_mnt_ns(pid: i32) -> Result<()> {
+    match setns_via_pidfd(pid) {
+        Ok(()) => Ok(()),
+        Err(e)
+            if e.raw_os_error() == Some(libc::EINVAL) || e.raw_os_error() == Some(libc::ENOSYS) =>
+        {
+            setns_via_proc(pid)
+        }
+        Err(e) => Err(e).context("setns via pidfd failed"),
+    }
+}
+
+fn setns_via_pidfd(pid: i32) -> std::io::Result<()> {
+    let pidfd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid, 0) };
+    if pidfd < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    let pidfd = pidfd as RawFd;
+    let ret = unsafe { libc::setns(pidfd, libc::CLONE_NEWNS) };
+    // Capture errno from `setns` before `close` gets a chance to clobber it.
+    let err = (ret != 0).then(std::io::Error::last_os_error);
+    unsafe { libc::close(pidfd) };
+    match err {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+fn setns_via_proc(pid: i32) -> Result<()> {
+    let path = format!("/proc/{pid}/ns/mnt");
+    let file = std::fs::File::open(&path).with_context(|| format!("can't open {path}"))?;
+    let ret = unsafe { libc::setns(std::os::unix::io::AsRawFd::as_raw_fd(&file), libc::CLONE_NEWNS) };
+    if ret != 0 {
+        return Err(std::io::Error::last_os_error()).with_context(|| format!("setns({path}) failed"));
+    }
+    Ok(())
+}
+
+/// Detach the calling process into a private copy of its current mount
+/// namespace, so module mounts don't leak back into the namespace entered
+/// by [`switch_mnt_ns`].
+pub fn unshare_mnt_ns() -> Result<()> {
+    if unsafe { libc::unshare(libc::CLONE_NEWNS) } != 0 {
+        return Err(std::io::Error::last_os_error()).context("unshare(CLONE_NEWNS) failed");
+    }
+    Ok(())
+}
+
+/// Copy `src` to `dst` with `std::fs::copy`; holes in `src` are not
+/// guaranteed to be kept.
+pub fn copy_sparse_file(src: String, dst: String) -> Result<()> {
+    std::fs::copy(&src, &dst).with_context(|| format!("failed to copy {src} to {dst}"))?;
+    Ok(())
+}
+
+/// Logs `file`; no hole is punched and no blocks are released.
+pub fn punch_hole(file: String) -> Result<()> {
+    log::info!("punch_hole: {}", file);
+    Ok(())
+}
+
+/// Block until the Android system property `name` equals `value`, or
+/// `timeout` elapses. Unlike a naive `__system_property_find` poll, this
+/// also handles properties that don't exist yet: while the property is
+/// missing it waits on the global property-area serial via
+/// `__system_property_wait`/`__system_property_area_serial` and re-scans
+/// every time the area changes, instead of giving up immediately.
+///
+/// Returns `Ok(true)` once the value matches, `Ok(false)` on timeout.
+#[cfg(target_os = "android")]
+pub fn wait_for_prop(name: &str, value: &str, timeout: std::time::Duration) -> Result<bool> {
+    use std::time::Instant;
+
+    let deadline = Instant::now() + timeout;
+    let mut serial: u32 = 0;
+
+    loop {
+        let pi = sysprop::find(name);
+        if let Some(pi) = pi {
+            if sysprop::read(pi) == value {
+                return Ok(true);
+            }
+            serial = sysprop::serial(pi);
+        } else {
+            serial = sysprop::area_serial();
+        }
+
+        let Some(remaining) = deadline.checked_duration_since(Instant::now()) else {
+            return Ok(false);
+        };
+        if !sysprop::wait(pi, serial, remaining) {
+            return Ok(false);
+        }
+    }
+}
+
+#[cfg(not(target_os = "android"))]
+pub fn wait_for_prop(_name: &str, _value: &str, _timeout: std::time::Duration) -> Result<bool> {
+    anyhow::bail!("system properties are only available on Android")
+}
+
+/// Thin, safe wrappers around bionic's `<sys/system_properties.h>` API.
+#[cfg(target_os = "android")]
+mod sysprop {
+    use std::ffi::{c_char, c_void, CStr, CString};
+
+    #[repr(C)]
+    pub struct PropInfo {
+        _private: [u8; 0],
+    }
+
+    extern "C" {
+        fn __system_property_find(name: *const c_char) -> *const PropInfo;
+        fn __system_property_serial(pi: *const PropInfo) -> u32;
+        fn __system_property_area_serial() -> u32;
+        fn __system_property_wait(
+            pi: *const PropInfo,
+            old_serial: u32,
+            new_serial_ptr: *mut u32,
+            timeout: *const libc::timespec,
+        ) -> bool;
+        fn __system_property_read_callback(
+            pi: *const PropInfo,
+            callback: extern "C" fn(*mut c_void, *const c_char, *const c_char, u32),
+            cookie: *mut c_void,
+        );
+    }
+
+    pub fn find(name: &str) -> Option<*const PropInfo> {
+        let name = CString::new(name).ok()?;
+        let pi = unsafe { __system_property_find(name.as_ptr()) };
+        (!pi.is_null()).then_some(pi)
+    }
+
+    pub fn serial(pi: *const PropInfo) -> u32 {
+        unsafe { __system_property_serial(pi) }
+    }
+
+    pub fn area_serial() -> u32 {
+        unsafe { __system_property_area_serial() }
+    }
+
+    pub fn read(pi: *const PropInfo) -> String {
+        extern "C" fn callback(cookie: *mut c_void, _name: *const c_char, value: *const c_char, _serial: u32) {
+            let out = cookie as *mut String;
+            let value = unsafe { CStr::from_ptr(value) }.to_string_lossy().into_owned();
+            unsafe { *out = value };
+        }
+
+        let mut value = String::new();
+        unsafe {
+            __system_property_read_callback(pi, callback, &mut value as *mut String as *mut c_void);
+        }
+        value
+    }
+
+    /// Wait for either `pi`'s serial (if it already existed) or the global
+    /// property-area serial (if it didn't) to move past `old_serial`.
+    /// Returns `false` if `timeout` elapses first.
+    pub fn wait(pi: Option<*const PropInfo>, old_serial: u32, timeout: std::time::Duration) -> bool {
+        let ts = libc::timespec {
+            tv_sec: timeout.as_secs() as libc::time_t,
+            tv_nsec: timeout.subsec_nanos() as i64,
+        };
+        let mut new_serial = 0u32;
+        unsafe {
+            __system_property_wait(
+                pi.unwrap_or(std::ptr::null()),
+                old_serial,
+                &mut new_serial,
+                &ts,
+            )
+        }
+    }
+}